@@ -0,0 +1,53 @@
+use crate::project::DataSet;
+
+/// Normalize a [DataSet] name (e.g. "Points remaining") to the form used in
+/// a `notify_when` rule (e.g. `points_remaining`).
+fn normalize(name: &str) -> String {
+    name.to_ascii_lowercase().replace(' ', "_")
+}
+
+fn latest_value(data: &[DataSet], name: &str) -> Option<f64> {
+    data.iter()
+        .find(|d| normalize(&d.name) == name)
+        .and_then(|d| d.data.last())
+        .map(|p| p[1])
+}
+
+/// `true` if the tail of the `rmsd` series increased from `old` to `new`.
+fn rmsd_increase(old: &[DataSet], new: &[DataSet]) -> bool {
+    match (latest_value(old, "rmsd"), latest_value(new, "rmsd")) {
+        (Some(old), Some(new)) => new > old,
+        _ => false,
+    }
+}
+
+/// `true` if the last point of the named series equals `target`.
+fn equals(new: &[DataSet], name: &str, target: f64) -> bool {
+    latest_value(new, name).is_some_and(|v| v == target)
+}
+
+/// Evaluate a `notify_when` rule against a project's data before (`old`) and
+/// after (`new`) a fetch. Supports `rmsd_increase` and `<series> == <value>`,
+/// e.g. `points_remaining == 0`.
+pub(crate) fn check(rule: &str, old: &[DataSet], new: &[DataSet]) -> bool {
+    if rule.trim() == "rmsd_increase" {
+        return rmsd_increase(old, new);
+    }
+    if let Some((name, value)) = rule.split_once("==") {
+        if let Ok(target) = value.trim().parse() {
+            return equals(new, name.trim(), target);
+        }
+    }
+    false
+}
+
+/// Fire an OS notification for `project`.
+pub(crate) fn notify(project: &str, message: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .summary(project)
+        .body(message)
+        .show()
+    {
+        eprintln!("failed to send notification: {e}");
+    }
+}
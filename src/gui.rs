@@ -1,7 +1,11 @@
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     process::{Command, Stdio},
-    sync::mpsc::{channel, Receiver, Sender},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
     thread,
     time::{Duration, Instant},
 };
@@ -13,18 +17,39 @@ use egui::{
 };
 
 use crate::project::{default_interval, Config, Project, ProjectType};
+use crate::ssh;
+use crate::store::Store;
+
+/// State of an in-flight (or not) fetch for a single project, shared between
+/// the worker pool and the GUI so it can render an "updating..." indicator.
+#[derive(Clone, Default)]
+pub(crate) enum FetchStatus {
+    #[default]
+    Idle,
+    Queued,
+    Fetching,
+    Error(String),
+}
 
 pub(crate) struct MyApp {
     temp: PathBuf,
     config: Config,
-    sender: Sender<(usize, PathBuf, Project)>,
-    receiver: Receiver<(usize, Project)>,
+    sender: Sender<(u64, PathBuf, Project)>,
+    receiver: Receiver<(u64, Project)>,
+
+    /// per-project fetch status, keyed by [Project::id] rather than its
+    /// (possibly shifting) position in `config.projects`, shared with the
+    /// worker pool
+    statuses: Arc<Mutex<HashMap<u64, FetchStatus>>>,
 
     show_add: bool,
     show_add_name: String,
     show_add_host: String,
     show_add_path: String,
     show_add_type: String,
+    /// set when "Add" is clicked with an unrecognized project type, shown in
+    /// the "Add a project" window instead of panicking
+    add_error: Option<String>,
 
     /// vec of indices to delete from config.projects at the end of the plotting
     /// loop
@@ -32,29 +57,70 @@ pub(crate) struct MyApp {
 }
 
 impl MyApp {
-    pub(crate) fn new(temp: PathBuf, config: Config) -> Self {
-        let (sender, inner_receiver) = channel::<(usize, PathBuf, Project)>();
+    pub(crate) fn new(
+        temp: PathBuf,
+        config: Config,
+        store_path: PathBuf,
+    ) -> Self {
+        let (sender, inner_receiver) = channel::<(u64, PathBuf, Project)>();
+        let inner_receiver = Arc::new(Mutex::new(inner_receiver));
         let (inner_sender, receiver) = channel();
 
-        thread::spawn(move || {
-            while let Ok((i, temp, mut project)) = inner_receiver.recv() {
-                project.update(temp).unwrap();
-                inner_sender.send((i, project)).unwrap();
-            }
-        });
+        let statuses = Arc::new(Mutex::new(
+            config
+                .projects
+                .iter()
+                .map(|p| (p.id, FetchStatus::Idle))
+                .collect::<HashMap<_, _>>(),
+        ));
+
+        for _ in 0..config.max_concurrent_fetches.max(1) {
+            let inner_receiver = inner_receiver.clone();
+            let inner_sender = inner_sender.clone();
+            let statuses = statuses.clone();
+            let store_path = store_path.clone();
+            thread::spawn(move || {
+                let store = Store::open(store_path)
+                    .expect("failed to open dash store");
+                loop {
+                    let job = inner_receiver.lock().unwrap().recv();
+                    let Ok((id, temp, mut project)) = job else {
+                        break;
+                    };
+                    statuses.lock().unwrap().insert(id, FetchStatus::Fetching);
+                    let result = project.update(temp, &store);
+                    let status = match &result {
+                        Ok(()) => FetchStatus::Idle,
+                        Err(e) => FetchStatus::Error(e.to_string()),
+                    };
+                    statuses.lock().unwrap().insert(id, status);
+                    inner_sender.send((id, project)).unwrap();
+                }
+            });
+        }
 
-        Self {
+        let mut app = Self {
             config,
             sender,
             receiver,
             temp,
+            statuses,
             show_add: false,
             show_add_name: String::new(),
             show_add_host: String::new(),
             show_add_path: String::new(),
             show_add_type: String::new(),
+            add_error: None,
             to_delete: Vec::new(),
+        };
+
+        // `Config::load` only hydrates from the `Store`; queue the first
+        // real fetch for every project here instead of blocking on it there
+        for i in 0..app.config.projects.len() {
+            app.request_update(i);
         }
+
+        app
     }
 
     /// Queue an update request for the project in `idx`
@@ -63,8 +129,9 @@ impl MyApp {
         // project
         self.config.projects[idx].last_updated = Instant::now();
         let p = &self.config.projects[idx];
+        self.statuses.lock().unwrap().insert(p.id, FetchStatus::Queued);
         self.sender
-            .send((idx, self.temp.clone(), p.clone()))
+            .send((p.id, self.temp.clone(), p.clone()))
             .unwrap();
     }
 
@@ -93,20 +160,43 @@ impl MyApp {
                 ui.label("type");
                 ui.text_edit_singleline(&mut self.show_add_type);
 
+                if let Some(err) = &self.add_error {
+                    ui.colored_label(Color32::RED, err);
+                }
+
                 if ui.button("Add").clicked() {
                     let typ = match self.show_add_type.as_str() {
-                        "pbqff" => ProjectType::Pbqff,
-                        "semp" => ProjectType::Semp,
-                        _ => panic!("invalid typ"),
+                        "pbqff" => Some(ProjectType::Pbqff),
+                        "semp" => Some(ProjectType::Semp),
+                        "custom" => Some(ProjectType::Custom),
+                        _ => None,
                     };
-                    self.config.projects.push(Project::new(
-                        std::mem::take(&mut self.show_add_name),
-                        std::mem::take(&mut self.show_add_host),
-                        std::mem::take(&mut self.show_add_path),
-                        typ,
-                    ));
-                    self.show_add_type.clear();
-                    self.request_update(self.config.projects.len() - 1);
+                    match typ {
+                        Some(typ) => {
+                            let project = Project::new(
+                                std::mem::take(&mut self.show_add_name),
+                                std::mem::take(&mut self.show_add_host),
+                                std::mem::take(&mut self.show_add_path),
+                                typ,
+                            );
+                            self.statuses
+                                .lock()
+                                .unwrap()
+                                .insert(project.id, FetchStatus::Idle);
+                            self.config.projects.push(project);
+                            self.show_add_type.clear();
+                            self.add_error = None;
+                            self.request_update(
+                                self.config.projects.len() - 1,
+                            );
+                        }
+                        None => {
+                            self.add_error = Some(format!(
+                                "unknown project type {:?}, expected \"pbqff\", \"semp\", or \"custom\"",
+                                self.show_add_type
+                            ));
+                        }
+                    }
                 }
 
                 if ui.button("Close").clicked() {
@@ -123,8 +213,10 @@ impl MyApp {
         // sort into descending order to remove from the end
         self.to_delete.sort_by(|a, b| b.cmp(a));
         let to_delete = std::mem::take(&mut self.to_delete);
+        let mut statuses = self.statuses.lock().unwrap();
         for i in to_delete {
-            self.config.projects.remove(i);
+            let project = self.config.projects.remove(i);
+            statuses.remove(&project.id);
         }
     }
 }
@@ -142,6 +234,17 @@ impl App for MyApp {
                         frame.close();
                     }
                 });
+
+                let in_flight = self
+                    .statuses
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .filter(|s| matches!(s, FetchStatus::Fetching))
+                    .count();
+                if in_flight > 0 {
+                    ui.label(format!("{in_flight} fetch(es) in flight"));
+                }
             });
         });
 
@@ -171,9 +274,41 @@ impl App for MyApp {
                     .show(ctx, |ui| {
                         let project = &self.config.projects[i];
                         ui.label(format!(
-                            "last updated {}",
-                            project.last_modified
+                            "last updated {}{}",
+                            project.last_modified,
+                            if project.cached { " (cached)" } else { "" }
                         ));
+                        if project.alerted && !project.muted {
+                            ui.colored_label(
+                                Color32::YELLOW,
+                                "alert condition met",
+                            );
+                        }
+                        let status = self
+                            .statuses
+                            .lock()
+                            .unwrap()
+                            .get(&project.id)
+                            .cloned()
+                            .unwrap_or_default();
+                        match status {
+                            FetchStatus::Idle => (),
+                            FetchStatus::Queued => {
+                                ui.label("queued...");
+                            }
+                            FetchStatus::Fetching => {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("updating...");
+                                });
+                            }
+                            FetchStatus::Error(e) => {
+                                ui.colored_label(
+                                    Color32::RED,
+                                    format!("fetch error: {e}"),
+                                );
+                            }
+                        }
                         let response = Plot::new(&project.path)
                             // TODO remove this when I get an answer
                             // https://github.com/emilk/egui/discussions/3101 and
@@ -200,14 +335,16 @@ impl App for MyApp {
                                 let path =
                                     Path::new(&self.config.projects[i].path);
                                 let dir = path.parent().unwrap();
+                                let host = &self.config.projects[i].host;
+                                let control =
+                                    ssh::multiplex_arg(&self.temp, host);
                                 let mut cmd =
                                     Command::new(&self.config.terminal);
                                 cmd.arg("-e")
                                     .arg("bash")
                                     .arg("-c")
                                     .arg(format!(
-                                        "exec ssh -t {} 'cd {}; bash --login'",
-                                        self.config.projects[i].host,
+                                        "exec ssh -t {control} {host} 'cd {}; bash --login'",
                                         dir.display()
                                     ))
                                     .stdout(Stdio::null())
@@ -217,14 +354,29 @@ impl App for MyApp {
                             if ui.button("Remove Project").clicked() {
                                 self.request_removal(i);
                             }
+                            let muted =
+                                self.config.projects[i].muted;
+                            if ui
+                                .button(if muted { "Unmute" } else { "Mute" })
+                                .clicked()
+                            {
+                                self.config.projects[i].muted = !muted;
+                            }
                         });
                     });
             }
 
             self.do_removal();
 
-            while let Ok((idx, project)) = self.receiver.try_recv() {
-                self.config.projects[idx] = project;
+            while let Ok((id, project)) = self.receiver.try_recv() {
+                // the project may have been removed while its fetch was in
+                // flight, so look up its current position by stable id
+                // rather than trusting a live vector index
+                if let Some(pos) =
+                    self.config.projects.iter().position(|p| p.id == id)
+                {
+                    self.config.projects[pos].apply_fetch(project);
+                }
             }
         });
     }
@@ -1,7 +1,5 @@
 //! Improvements:
 //!
-//! Request update on initial project set instead of downloading up font in load
-//!
 //! Try to check hash of file before copying it over (sha1sum?), although that
 //! will likely add at least one ssh call
 
@@ -18,8 +16,11 @@ use std::{
 use gui::MyApp;
 use project::Config;
 
+mod alerts;
 mod gui;
 mod project;
+mod ssh;
+mod store;
 
 /// create a temporary directory and return its path
 fn tempdir() -> io::Result<std::path::PathBuf> {
@@ -75,9 +76,10 @@ fn main() -> anyhow::Result<()> {
         config
     };
 
-    let projects = Config::load(infile, &temp)?;
+    let store_path = project::store_path(&infile);
+    let projects = Config::load(infile)?;
 
-    let app = MyApp::new(temp.clone(), projects);
+    let app = MyApp::new(temp.clone(), projects, store_path);
 
     eframe::run_native(
         PROGRAM_TITLE,
@@ -86,6 +88,8 @@ fn main() -> anyhow::Result<()> {
     )
     .unwrap();
 
+    ssh::shutdown();
+
     match remove_dir_all(temp) {
         Ok(_) => (),
         Err(e) => eprintln!("{e}"),
@@ -2,13 +2,19 @@ use std::fs::read_to_string;
 use std::io::Read;
 use std::path::Path;
 use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
 use chrono::DateTime;
 use chrono::Local;
+use chrono::SubsecRound;
+use regex::Regex;
 use serde::Deserialize;
 
 use super::DEBUG;
+use crate::alerts;
+use crate::ssh;
+use crate::store::Store;
 
 pub(crate) const fn default_interval() -> u64 {
     600
@@ -18,12 +24,58 @@ fn default_terminal() -> String {
     "st".to_string()
 }
 
+pub(crate) const fn default_max_concurrent_fetches() -> usize {
+    4
+}
+
+static NEXT_PROJECT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Assign a new, never-reused id, used to track a [Project] across the GUI's
+/// worker pool even after its position in `config.projects` shifts.
+fn next_project_id() -> u64 {
+    NEXT_PROJECT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 #[derive(Clone, Copy, Deserialize)]
 pub(crate) enum ProjectType {
     #[serde(alias = "semp")]
     Semp,
     #[serde(alias = "pbqff")]
     Pbqff,
+    #[serde(alias = "custom")]
+    Custom,
+}
+
+/// One series to extract from a [`ProjectType::Custom`] project's log, given
+/// by a line-matching regex and where in it to find the x and y values.
+/// Exactly one of `x_group`/`x_column` and one of `y_group`/`y_column` must
+/// be given.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SeriesSpec {
+    pub(crate) name: String,
+
+    /// regex tested against every line; lines that don't match are skipped
+    pub(crate) pattern: String,
+
+    /// capture group (1-indexed) holding the x value
+    #[serde(default)]
+    pub(crate) x_group: Option<usize>,
+    /// whitespace-split column (0-indexed) holding the x value
+    #[serde(default)]
+    pub(crate) x_column: Option<usize>,
+
+    /// capture group (1-indexed) holding the y value
+    #[serde(default)]
+    pub(crate) y_group: Option<usize>,
+    /// whitespace-split column (0-indexed) holding the y value
+    #[serde(default)]
+    pub(crate) y_column: Option<usize>,
+
+    /// when a line matches this regex, clear the series' accumulated data,
+    /// mirroring the `pbqff` phase-drop behavior
+    #[serde(default)]
+    pub(crate) reset_on: Option<String>,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -33,7 +85,15 @@ pub(crate) struct DataSet {
 }
 
 #[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct Project {
+    /// stable identifier, assigned once and never reused, so the GUI's
+    /// worker pool can match an in-flight fetch back to the right project
+    /// even after `config.projects` has been reordered or shrunk
+    #[serde(default = "next_project_id")]
+    #[serde(skip_deserializing)]
+    pub(crate) id: u64,
+
     pub(crate) name: String,
     pub(crate) host: String,
     pub(crate) path: String,
@@ -41,6 +101,28 @@ pub(crate) struct Project {
     #[serde(alias = "type")]
     pub(crate) typ: ProjectType,
 
+    /// series definitions for a `ProjectType::Custom` project; unused
+    /// otherwise
+    #[serde(default)]
+    pub(crate) series: Vec<SeriesSpec>,
+
+    /// alert rule evaluated after each successful fetch, e.g.
+    /// `"points_remaining == 0"` or `"rmsd_increase"`; see [crate::alerts]
+    #[serde(default)]
+    pub(crate) notify_when: Option<String>,
+
+    /// suppresses [crate::alerts::notify] when set, toggled from the
+    /// per-project context menu
+    #[serde(default)]
+    #[serde(skip_deserializing)]
+    pub(crate) muted: bool,
+
+    /// whether `notify_when` fired on the last update, so a notification is
+    /// only sent once per transition rather than every poll
+    #[serde(default)]
+    #[serde(skip_deserializing)]
+    pub(crate) alerted: bool,
+
     #[serde(default = "Instant::now")]
     #[serde(skip_deserializing)]
     pub(crate) last_updated: Instant,
@@ -56,11 +138,41 @@ pub(crate) struct Project {
     #[serde(default)]
     #[serde(skip_deserializing)]
     pub(crate) last_modified: DateTime<Local>,
+
+    /// size in bytes of the remote file as of the last successful fetch, used
+    /// alongside `last_modified` to detect an unchanged remote file
+    #[serde(default)]
+    #[serde(skip_deserializing)]
+    pub(crate) last_size: u64,
+
+    /// set by [Project::update] to indicate the last fetch was skipped
+    /// because the remote file hadn't changed, for display in the GUI
+    #[serde(default)]
+    #[serde(skip_deserializing)]
+    pub(crate) cached: bool,
+
+    /// for a `pbqff` project, the number of `"finished dropping"` markers
+    /// [parse_pbqff] has counted in the remote log so far, so historical
+    /// points from earlier phases aren't overwritten in the [Store]
+    #[serde(default)]
+    #[serde(skip_deserializing)]
+    pub(crate) phase: i64,
 }
 
-pub(crate) struct Fetch {
-    pub(crate) last_modified: DateTime<Local>,
-    pub(crate) data: Vec<DataSet>,
+pub(crate) enum Fetch {
+    /// the remote file hasn't changed since the last fetch, so `data` and
+    /// `last_modified` on the [Project] should be left alone
+    Unchanged,
+
+    /// the remote file changed and was re-fetched and re-parsed
+    Updated {
+        last_modified: DateTime<Local>,
+        last_size: u64,
+        data: Vec<DataSet>,
+        /// the pbqff phase, from [parse_pbqff]; `None` for other project
+        /// types, which have no notion of a phase
+        phase: Option<i64>,
+    },
 }
 
 /// parse a semp freqs.log file
@@ -109,15 +221,22 @@ pub(crate) fn parse_semp(contents: String) -> Vec<DataSet> {
     vec![norm, rmsd, max]
 }
 
-pub(crate) fn parse_pbqff(contents: String) -> Vec<DataSet> {
+/// Parse a pbqff output file, returning the "Points remaining" series
+/// alongside the phase, i.e. the number of `"finished dropping"` markers
+/// seen so far. Since the whole remote log is re-fetched every time, this
+/// count is authoritative and absolute, rather than something the caller
+/// has to infer by comparing iteration numbers across polls.
+pub(crate) fn parse_pbqff(contents: String) -> (i64, Vec<DataSet>) {
     let mut ret = DataSet {
         name: "Points remaining".to_owned(),
         data: Vec::new(),
     };
+    let mut phase = 0;
     let mut did_drop = false;
     for line in contents.lines() {
         if line.starts_with("finished dropping") {
             did_drop = true;
+            phase += 1;
         }
         if line.starts_with("[iter ") {
             // only track the current phase of the QFF. if we dropped and
@@ -132,10 +251,101 @@ pub(crate) fn parse_pbqff(contents: String) -> Vec<DataSet> {
             ret.data.push([i, remaining]);
         }
     }
-    vec![ret]
+    (phase, vec![ret])
+}
+
+/// pull the x or y value out of a matched line, preferring the capture
+/// group when both a group and a column are given
+fn extract_field(
+    caps: &regex::Captures,
+    line: &str,
+    group: Option<usize>,
+    column: Option<usize>,
+) -> Option<f64> {
+    if let Some(g) = group {
+        return caps.get(g)?.as_str().parse().ok();
+    }
+    let c = column?;
+    line.split_ascii_whitespace().nth(c)?.parse().ok()
+}
+
+/// validate that exactly one of `x_group`/`x_column` and exactly one of
+/// `y_group`/`y_column` is set, as documented on [SeriesSpec], so a
+/// misconfigured series fails loudly instead of silently dropping points
+fn validate_series(spec: &SeriesSpec) -> anyhow::Result<()> {
+    if spec.x_group.is_some() == spec.x_column.is_some() {
+        anyhow::bail!(
+            "series {:?}: exactly one of x_group/x_column must be given",
+            spec.name
+        );
+    }
+    if spec.y_group.is_some() == spec.y_column.is_some() {
+        anyhow::bail!(
+            "series {:?}: exactly one of y_group/y_column must be given",
+            spec.name
+        );
+    }
+    Ok(())
+}
+
+/// parse a project's log according to its user-defined `series` specs
+pub(crate) fn parse_custom(
+    contents: String,
+    series: &[SeriesSpec],
+) -> anyhow::Result<Vec<DataSet>> {
+    struct Compiled<'a> {
+        spec: &'a SeriesSpec,
+        pattern: Regex,
+        reset_on: Option<Regex>,
+    }
+
+    let compiled = series
+        .iter()
+        .map(|spec| {
+            validate_series(spec)?;
+            Ok(Compiled {
+                spec,
+                pattern: Regex::new(&spec.pattern)?,
+                reset_on: spec
+                    .reset_on
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut datasets: Vec<DataSet> = compiled
+        .iter()
+        .map(|c| DataSet {
+            name: c.spec.name.clone(),
+            data: Vec::new(),
+        })
+        .collect();
+
+    for line in contents.lines() {
+        for (c, ds) in compiled.iter().zip(datasets.iter_mut()) {
+            if c.reset_on.as_ref().is_some_and(|r| r.is_match(line)) {
+                ds.data.clear();
+            }
+            let Some(caps) = c.pattern.captures(line) else {
+                continue;
+            };
+            let x =
+                extract_field(&caps, line, c.spec.x_group, c.spec.x_column);
+            let y =
+                extract_field(&caps, line, c.spec.y_group, c.spec.y_column);
+            if let (Some(x), Some(y)) = (x, y) {
+                ds.data.push([x, y]);
+            }
+        }
+    }
+
+    Ok(datasets)
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct Config {
     #[serde(default)]
     #[serde(rename = "project")]
@@ -143,26 +353,44 @@ pub(crate) struct Config {
 
     #[serde(default = "default_terminal")]
     pub(crate) terminal: String,
+
+    /// maximum number of fetches (scp/ssh calls) to run at once across all
+    /// projects
+    #[serde(default = "default_max_concurrent_fetches")]
+    pub(crate) max_concurrent_fetches: usize,
 }
 
 impl Config {
-    /// Deserialize a set of [Project]s from the TOML file at `path`, and update
-    /// them using [Project::update].
-    pub(crate) fn load(
-        path: impl AsRef<Path>,
-        temp: impl AsRef<Path>,
-    ) -> anyhow::Result<Self> {
-        let toml = read_to_string(path)?;
+    /// Deserialize a set of [Project]s from the TOML file at `path` and
+    /// hydrate each from the on-disk [Store] next to `path`, so plots are
+    /// populated immediately. The first real fetch is left to the caller
+    /// (the GUI's worker pool), so this never blocks on the network.
+    pub(crate) fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let toml = read_to_string(&path)?;
         let mut projects: Config = toml::from_str(&toml)?;
 
+        let store = Store::open(store_path(&path))?;
+
         for p in projects.projects.iter_mut() {
-            p.update(&temp)?;
+            let (phase, data) = store.load_latest_phase(&p.name)?;
+            p.phase = phase;
+            p.data = data;
         }
 
         Ok(projects)
     }
 }
 
+/// The path of the SQLite database backing a [Store], kept next to the
+/// config file at `config_path`.
+pub(crate) fn store_path(config_path: impl AsRef<Path>) -> std::path::PathBuf {
+    config_path
+        .as_ref()
+        .parent()
+        .map(|p| p.join("dash.sqlite3"))
+        .unwrap_or_else(|| std::path::PathBuf::from("dash.sqlite3"))
+}
+
 impl Project {
     pub(crate) fn new(
         name: String,
@@ -171,29 +399,79 @@ impl Project {
         typ: ProjectType,
     ) -> Self {
         Self {
+            id: next_project_id(),
             name,
             host,
             path,
             typ,
+            series: Vec::new(),
+            notify_when: None,
+            muted: false,
+            alerted: false,
             last_updated: Instant::now(),
             update_interval: default_interval(),
             data: Vec::new(),
             last_modified: Default::default(),
+            last_size: 0,
+            cached: false,
+            phase: 0,
         }
     }
 
+    /// Probe the remote file's modification time and size with a single
+    /// `ssh stat` round-trip, without transferring the file itself. Returns
+    /// `None` if the remote `stat` call fails for any reason (e.g. the host
+    /// doesn't have `stat`, or it doesn't understand `-c`), in which case the
+    /// caller should fall back to an unconditional copy.
+    fn probe(&self, temp: impl AsRef<Path>) -> Option<(DateTime<Local>, u64)> {
+        let mut cmd = Command::new("ssh");
+        ssh::multiplex(&mut cmd, temp, &self.host);
+        let output = cmd
+            .arg(&self.host)
+            .arg("stat")
+            .arg("-c")
+            .arg("%Y %s")
+            .arg(&self.path)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8(output.stdout).ok()?;
+        let mut sp = stdout.split_ascii_whitespace();
+        let secs: i64 = sp.next()?.parse().ok()?;
+        let size: u64 = sp.next()?.parse().ok()?;
+        let modified = DateTime::from_timestamp(secs, 0)?.with_timezone(&Local);
+        Some((modified, size))
+    }
+
     /// Retrieve the remote files for `self`, storing temporary files in `temp`.
-    /// Returns a [Fetch] containing the resulting data.
+    /// Returns a [Fetch] containing the resulting data, or [Fetch::Unchanged]
+    /// if a cheap `stat` probe shows the remote file hasn't changed.
     pub(crate) fn fetch(
         &self,
         temp: impl AsRef<Path>,
     ) -> anyhow::Result<Fetch> {
+        if let Some((modified, size)) = self.probe(temp.as_ref()) {
+            if modified == self.last_modified && size == self.last_size {
+                if *DEBUG {
+                    eprintln!(
+                        "skipping unchanged {} at {}",
+                        self.path,
+                        Local::now()
+                    );
+                }
+                return Ok(Fetch::Unchanged);
+            }
+        }
+
         let path = format!("{host}:{path}", host = self.host, path = self.path);
         let output = temp.as_ref().join("path.dat");
         if *DEBUG {
             eprintln!("calling fetch on {path} at {}", Local::now());
         }
         let mut cmd = Command::new("scp");
+        ssh::multiplex(&mut cmd, &temp, &self.host);
         cmd.arg("-p") // preserve mod times
             .arg("-C") // use compression
             .arg(path)
@@ -202,11 +480,15 @@ impl Project {
         let mut file = std::fs::File::open(output)?;
         let meta = file.metadata()?;
         let modified = meta.modified()?;
-        let last_modified: DateTime<Local> = DateTime::from(modified);
+        // `probe`'s remote `stat` only has second resolution, so truncate
+        // here too or the unchanged-file check above would never match
+        let last_modified: DateTime<Local> =
+            DateTime::from(modified).trunc_subsecs(0);
+        let size = meta.len();
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
 
-        let data = match self.typ {
+        let (phase, data) = match self.typ {
             ProjectType::Semp => {
                 let mut data = parse_semp(contents);
                 // this will be the path of the semp.out file, so get the parent
@@ -220,6 +502,7 @@ impl Project {
                 );
                 let output = temp.as_ref().join("freqs.log");
                 let mut cmd = Command::new("scp");
+                ssh::multiplex(&mut cmd, &temp, &self.host);
                 cmd.arg("-p") // preserve mod times
                     .arg("-C") // use compression
                     .arg(path)
@@ -229,14 +512,20 @@ impl Project {
                 let mut contents = String::new();
                 file.read_to_string(&mut contents)?;
                 data.push(parse_freqs(contents));
-                data
+                (None, data)
+            }
+            ProjectType::Pbqff => {
+                let (phase, data) = parse_pbqff(contents);
+                (Some(phase), data)
             }
-            ProjectType::Pbqff => parse_pbqff(contents),
+            ProjectType::Custom => (None, parse_custom(contents, &self.series)?),
         };
 
-        Ok(Fetch {
+        Ok(Fetch::Updated {
             last_modified,
+            last_size: size,
             data,
+            phase,
         })
     }
 
@@ -248,14 +537,57 @@ impl Project {
     pub(crate) fn update(
         &mut self,
         temp: impl AsRef<Path>,
+        store: &Store,
     ) -> anyhow::Result<()> {
-        let Fetch {
-            last_modified,
-            data,
-        } = self.fetch(temp)?;
-        self.data = data;
         self.last_updated = Instant::now();
-        self.last_modified = last_modified;
-        Ok(())
+        match self.fetch(temp)? {
+            Fetch::Unchanged => {
+                self.cached = true;
+                Ok(())
+            }
+            Fetch::Updated {
+                last_modified,
+                last_size,
+                data,
+                phase,
+            } => {
+                if let Some(phase) = phase {
+                    self.phase = phase;
+                }
+                for ds in &data {
+                    store.upsert(&self.name, self.phase, ds, last_modified)?;
+                }
+                if let Some(rule) = &self.notify_when {
+                    let fires = alerts::check(rule, &self.data, &data);
+                    if fires && !self.alerted && !self.muted {
+                        alerts::notify(
+                            &self.name,
+                            &format!("alert fired: {rule}"),
+                        );
+                    }
+                    self.alerted = fires;
+                }
+                self.data = data;
+                self.last_modified = last_modified;
+                self.last_size = last_size;
+                self.cached = false;
+                Ok(())
+            }
+        }
+    }
+
+    /// Merge the fields [Project::update] sets on `fetched` (a clone of
+    /// `self` captured before being handed to the worker pool) back into
+    /// `self`, leaving every other field alone. `last_updated` is not copied
+    /// either: `self` keeps the time the fetch was queued. This way per-project GUI
+    /// state the user changed (e.g. `muted`) while the fetch was in flight
+    /// isn't clobbered by the stale clone.
+    pub(crate) fn apply_fetch(&mut self, fetched: Project) {
+        self.data = fetched.data;
+        self.last_modified = fetched.last_modified;
+        self.last_size = fetched.last_size;
+        self.phase = fetched.phase;
+        self.cached = fetched.cached;
+        self.alerted = fetched.alerted;
     }
 }
@@ -0,0 +1,83 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::{LazyLock, Mutex},
+};
+
+use crate::project::default_interval;
+
+/// ControlMaster connections, one per host, shared across every
+/// [Project](crate::project::Project) so repeated scp/ssh calls to the same
+/// host reuse a single authenticated channel instead of paying the
+/// handshake/auth cost every time.
+static MASTERS: LazyLock<Mutex<HashMap<String, Child>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// how long an idle ControlMaster connection is kept open for reuse; matches
+/// the default project poll interval so the master is still alive the next
+/// time it's needed instead of exiting between polls
+const CONTROL_PERSIST_SECS: u64 = default_interval();
+
+fn control_path(temp: impl AsRef<Path>, host: &str) -> PathBuf {
+    temp.as_ref().join(format!("ctl-{host}"))
+}
+
+/// Ensure a ControlMaster connection to `host` is running, starting one if
+/// necessary, and return the path of its control socket. Reaps a master that
+/// has exited (e.g. its `ControlPersist` timed out) so a dead entry doesn't
+/// linger in `MASTERS` pointing callers at a gone socket.
+fn ensure_master(temp: impl AsRef<Path>, host: &str) -> PathBuf {
+    let path = control_path(&temp, host);
+    let mut masters = MASTERS.lock().unwrap();
+    if let Some(child) = masters.get_mut(host) {
+        if !matches!(child.try_wait(), Ok(None)) {
+            masters.remove(host);
+        }
+    }
+    if !masters.contains_key(host) {
+        if let Ok(child) = Command::new("ssh")
+            .arg("-M")
+            .arg("-S")
+            .arg(&path)
+            .arg("-o")
+            .arg(format!("ControlPersist={CONTROL_PERSIST_SECS}"))
+            .arg("-N")
+            .arg(host)
+            .spawn()
+        {
+            masters.insert(host.to_string(), child);
+        }
+    }
+    path
+}
+
+/// Add `-o ControlPath=<socket>` to `cmd` for `host`, starting its
+/// ControlMaster connection first if one isn't already running. Call this
+/// before every `scp`/`ssh` invocation that talks to `host`.
+pub(crate) fn multiplex(
+    cmd: &mut Command,
+    temp: impl AsRef<Path>,
+    host: &str,
+) {
+    let path = ensure_master(temp, host);
+    cmd.arg("-o").arg(format!("ControlPath={}", path.display()));
+}
+
+/// The `-o ControlPath=...` argument for `host`, as a single string, for
+/// callers (like a shell command string) that can't build up a [Command]
+/// directly. Starts the ControlMaster connection first if necessary.
+pub(crate) fn multiplex_arg(temp: impl AsRef<Path>, host: &str) -> String {
+    let path = ensure_master(temp, host);
+    format!("-o ControlPath={}", path.display())
+}
+
+/// Tear down every open ControlMaster connection. Call this once on exit,
+/// alongside removing the temp dir the control sockets live in.
+pub(crate) fn shutdown() {
+    let mut masters = MASTERS.lock().unwrap();
+    for (_, mut child) in masters.drain() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection};
+
+use crate::project::DataSet;
+
+/// On-disk history for [Project](crate::project::Project) data, keyed by
+/// project name, dataset name, and phase. Backed by a small SQLite database
+/// so plots survive a restart and a `pbqff` phase reset doesn't erase older
+/// points.
+pub(crate) struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Open (creating if necessary) the database at `path`. Uses WAL mode
+    /// and a busy timeout so the worker pool's concurrent fetches can each
+    /// open their own [Connection] without tripping `SQLITE_BUSY` when two
+    /// upserts land close together.
+    pub(crate) fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS points (
+                project    TEXT NOT NULL,
+                dataset    TEXT NOT NULL,
+                phase      INTEGER NOT NULL,
+                x          REAL NOT NULL,
+                y          REAL NOT NULL,
+                fetched_at TEXT NOT NULL,
+                PRIMARY KEY (project, dataset, phase, x)
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Upsert every point in `dataset` for `project` at `phase`, stamped with
+    /// `fetched_at`.
+    pub(crate) fn upsert(
+        &self,
+        project: &str,
+        phase: i64,
+        dataset: &DataSet,
+        fetched_at: DateTime<Local>,
+    ) -> anyhow::Result<()> {
+        let fetched_at = fetched_at.to_rfc3339();
+        for [x, y] in &dataset.data {
+            self.conn.execute(
+                "INSERT INTO points (project, dataset, phase, x, y, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (project, dataset, phase, x)
+                 DO UPDATE SET y = excluded.y, fetched_at = excluded.fetched_at",
+                params![project, dataset.name, phase, x, y, fetched_at],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Load the most recently stored phase number and its points for
+    /// `project`, one [DataSet] per distinct dataset name, used to hydrate a
+    /// [Project] before its first fetch of a session. Returns `(0, vec![])`
+    /// if nothing has been stored for `project` yet.
+    pub(crate) fn load_latest_phase(
+        &self,
+        project: &str,
+    ) -> anyhow::Result<(i64, Vec<DataSet>)> {
+        let phase: Option<i64> = self.conn.query_row(
+            "SELECT MAX(phase) FROM points WHERE project = ?1",
+            params![project],
+            |row| row.get(0),
+        )?;
+        let Some(phase) = phase else {
+            return Ok((0, Vec::new()));
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT dataset, x, y FROM points
+             WHERE project = ?1 AND phase = ?2
+             ORDER BY dataset, x",
+        )?;
+        let rows = stmt.query_map(params![project, phase], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })?;
+
+        let mut datasets: Vec<DataSet> = Vec::new();
+        for row in rows {
+            let (name, x, y) = row?;
+            match datasets.iter_mut().find(|d| d.name == name) {
+                Some(ds) => ds.data.push([x, y]),
+                None => datasets.push(DataSet {
+                    name,
+                    data: vec![[x, y]],
+                }),
+            }
+        }
+        Ok((phase, datasets))
+    }
+}